@@ -1,8 +1,8 @@
-/* 
+/*
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: June 26, 2017
-Last Modified: June 26, 2017
+Last Modified: July 26, 2026
 License: MIT
 */
 extern crate time;
@@ -11,6 +11,7 @@ extern crate num_cpus;
 use std::env;
 use std::path;
 use std::f64;
+use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::sync::Arc;
 use std::sync::mpsc;
@@ -18,6 +19,13 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::WhiteboxTool;
+use tools::parameter_parsing::{split_args, load_param_file, lookup_param, UsizeParam};
+
+/// Number of bins used to approximate the distribution of values inside a
+/// moving window. This is fixed rather than scaled with the window size
+/// because Huang's sliding-histogram algorithm's cost is governed by the
+/// number of bins, not by the number of pixels in the window.
+const NUM_HISTOGRAM_BINS: usize = 256;
 
 pub struct OlympicFilter {
     name: String,
@@ -29,15 +37,17 @@ pub struct OlympicFilter {
 impl OlympicFilter {
     pub fn new() -> OlympicFilter { // public constructor
         let name = "OlympicFilter".to_string();
-        
-        let description = "Performs an olympic smoothing filter on an image.".to_string();
-        
+
+        let description = "Performs an alpha-trimmed-mean (olympic) smoothing filter on an image.".to_string();
+
         let mut parameters = "-i, --input   Input raster file.".to_owned();
         parameters.push_str("-o, --output  Output raster file.\n");
-        parameters.push_str("--filter      Size of the filter kernel (default is 11).\n");
-        parameters.push_str("--filterx     Optional size of the filter kernel in the x-direction (default is 11; not used if --filter is specified).\n");
-        parameters.push_str("--filtery     Optional size of the filter kernel in the y-direction (default is 11; not used if --filter is specified).\n");
-        
+        parameters.push_str("--filter      Size of the filter kernel; must be an odd integer >= 3 (default is 11).\n");
+        parameters.push_str("--filterx     Optional size of the filter kernel in the x-direction; must be an odd integer >= 3 (default is 11; not used if --filter is specified).\n");
+        parameters.push_str("--filtery     Optional size of the filter kernel in the y-direction; must be an odd integer >= 3 (default is 11; not used if --filter is specified).\n");
+        parameters.push_str("--trim        Number of lowest and highest values to trim from each window before averaging (default is 1, i.e. the classic olympic filter).\n");
+        parameters.push_str("--params      Optional JSON parameter file (e.g. --params=run.json) providing any of the above; command-line arguments take precedence over matching file values.\n");
+
         let sep: String = path::MAIN_SEPARATOR.to_string();
         let p = format!("{}", env::current_dir().unwrap().display());
         let e = format!("{}", env::current_exe().unwrap().display());
@@ -45,12 +55,328 @@ impl OlympicFilter {
         if e.contains(".exe") {
             short_exe += ".exe";
         }
-        let usage = format!(">>.*{} -r={} --wd=\"*path*to*data*\" -i=image.dep -o=output.dep --filter=25", short_exe, name).replace("*", &sep);
-    
+        let usage = format!(">>.*{} -r={} --wd=\"*path*to*data*\" -i=image.dep -o=output.dep --filter=25 --trim=2", short_exe, name).replace("*", &sep);
+
         OlympicFilter { name: name, description: description, parameters: parameters, example_usage: usage }
     }
 }
 
+/// Maps a data value onto one of `num_bins` equal-width bins spanning
+/// `[data_min, data_min + num_bins as f64 * bin_width]`.
+fn histogram_bin(value: f64, data_min: f64, bin_width: f64, num_bins: usize) -> usize {
+    if bin_width <= 0f64 {
+        return 0;
+    }
+    let idx = ((value - data_min) / bin_width) as isize;
+    if idx < 0 {
+        0
+    } else if idx >= num_bins as isize {
+        num_bins - 1
+    } else {
+        idx as usize
+    }
+}
+
+/// Builds the histogram of valid (non-nodata) values found in the column
+/// `col`, over the row range `start_row..=end_row`, along with their exact
+/// sum and count. Called once per column by `ColumnHistogramCache::new` when
+/// a thread's first window is initialized; later row and column advances
+/// update the cached histograms instead of calling this again.
+fn column_histogram(input: &Raster, col: isize, start_row: isize, end_row: isize, nodata: f64,
+                     data_min: f64, bin_width: f64, num_bins: usize) -> (Vec<u32>, f64, usize) {
+    let mut hist = vec![0u32; num_bins];
+    let mut sum = 0f64;
+    let mut n = 0usize;
+    for row2 in start_row..end_row + 1 {
+        let z_n = input[(row2, col)];
+        if z_n != nodata {
+            sum += z_n;
+            n += 1;
+            let bin = histogram_bin(z_n, data_min, bin_width, num_bins);
+            hist[bin] += 1;
+        }
+    }
+    (hist, sum, n)
+}
+
+/// Discards the `k` lowest and `k` highest counted samples from `window_hist`
+/// and returns the bin-center-weighted mean of what remains. Assumes the
+/// caller has already checked that `n > 2 * k`.
+fn trimmed_mean_from_histogram(window_hist: &[u32], k: usize, data_min: f64, bin_width: f64) -> f64 {
+    let num_bins = window_hist.len();
+    let mut skip_lo = k;
+    let mut skip_hi = k;
+    let mut lo: isize = 0;
+    let mut hi: isize = num_bins as isize - 1;
+    while lo <= hi && (window_hist[lo as usize] as usize) <= skip_lo {
+        skip_lo -= window_hist[lo as usize] as usize;
+        lo += 1;
+    }
+    while hi >= lo && (window_hist[hi as usize] as usize) <= skip_hi {
+        skip_hi -= window_hist[hi as usize] as usize;
+        hi -= 1;
+    }
+    let mut weighted_sum = 0f64;
+    let mut kept = 0usize;
+    let mut b = lo;
+    while b <= hi {
+        let mut count = window_hist[b as usize] as usize;
+        if b == lo {
+            count -= skip_lo;
+        }
+        if b == hi {
+            count -= skip_hi;
+        }
+        let center = data_min + (b as f64 + 0.5) * bin_width;
+        weighted_sum += center * count as f64;
+        kept += count;
+        b += 1;
+    }
+    if kept > 0 { weighted_sum / kept as f64 } else { 0f64 }
+}
+
+/// Maintains the vertical-window histogram of every column, updated
+/// incrementally as the output row advances: `advance_row` only touches the
+/// one row that departs and the one that arrives, so it costs O(columns)
+/// per row regardless of the kernel's height, rather than re-scanning each
+/// column's full vertical window (O(columns * filter_size_y)) every row.
+/// This mirrors `VerticalAggregator`, but for the per-bin distribution that
+/// `trimmed_mean_from_histogram` needs instead of just min/max/sum/count.
+struct ColumnHistogramCache {
+    num_bins: usize,
+    data_min: f64,
+    bin_width: f64,
+    hist: Vec<Vec<u32>>,
+    zero_hist: Vec<u32>,
+}
+
+impl ColumnHistogramCache {
+    /// Builds the initial vertical-window histogram for every column by
+    /// scanning `start_row..=end_row` once; this is the one O(columns *
+    /// filter_size_y) pass paid per thread, not per row.
+    fn new(input: &Raster, columns: usize, start_row: isize, end_row: isize, nodata: f64,
+           data_min: f64, bin_width: f64, num_bins: usize) -> ColumnHistogramCache {
+        let mut hist = Vec::with_capacity(columns);
+        for col in 0..columns {
+            let (col_hist, _, _) = column_histogram(input, col as isize, start_row, end_row, nodata,
+                data_min, bin_width, num_bins);
+            hist.push(col_hist);
+        }
+        ColumnHistogramCache {
+            num_bins: num_bins,
+            data_min: data_min,
+            bin_width: bin_width,
+            hist: hist,
+            zero_hist: vec![0u32; num_bins],
+        }
+    }
+
+    /// Slides every column's vertical window down by one row: removes
+    /// `departing_row`'s contribution and adds `arriving_row`'s.
+    fn advance_row(&mut self, input: &Raster, departing_row: isize, arriving_row: isize, nodata: f64) {
+        for col in 0..self.hist.len() {
+            let z_departing = input[(departing_row, col as isize)];
+            if z_departing != nodata {
+                let b = histogram_bin(z_departing, self.data_min, self.bin_width, self.num_bins);
+                self.hist[col][b] -= 1;
+            }
+            let z_arriving = input[(arriving_row, col as isize)];
+            if z_arriving != nodata {
+                let b = histogram_bin(z_arriving, self.data_min, self.bin_width, self.num_bins);
+                self.hist[col][b] += 1;
+            }
+        }
+    }
+
+    /// The vertical-window histogram for `col`, or an all-zero histogram for
+    /// a column outside the raster (consistent with `Raster`'s indexing,
+    /// which treats out-of-bounds cells as nodata).
+    fn get(&self, col: isize) -> &[u32] {
+        if col < 0 || col as usize >= self.hist.len() {
+            &self.zero_hist
+        } else {
+            &self.hist[col as usize]
+        }
+    }
+}
+
+fn validate_filter_size(n: usize) -> Result<(), String> {
+    if n < 3 || n % 2 == 0 {
+        return Err("filter size must be a positive odd integer >= 3.".to_string());
+    }
+    Ok(())
+}
+
+fn validate_trim(_n: usize) -> Result<(), String> {
+    Ok(())
+}
+
+/// The min, max, sum and valid-count of every column's horizontal window
+/// `[col - midpoint_x, col + midpoint_x]`, for a single row. Since min, max,
+/// sum and count are all separable over a rectangle, combining these
+/// per-row vectors down a column (see `VerticalAggregator`) yields the same
+/// statistics over the full 2-D window.
+struct RowWindowStats {
+    row: isize,
+    hmin: Vec<f64>,
+    hmax: Vec<f64>,
+    hsum: Vec<f64>,
+    hcount: Vec<usize>,
+}
+
+/// Computes `RowWindowStats` for `row` in a single left-to-right pass using
+/// the ascending/descending-minima monotonic-deque algorithm: the deques
+/// hold column indices whose cached values are monotonic, so the window
+/// min/max is always at the front, and are updated in amortized O(1) per
+/// column as the window's trailing and leading edges advance. `get` returns
+/// the value at a given column of `row` (or `nodata` outside the raster's
+/// bounds); it is factored out so the windowing logic can be unit tested
+/// without a real `Raster`.
+fn horizontal_window_stats_core<G: Fn(isize) -> f64>(row: isize, columns: isize, midpoint_x: isize, nodata: f64, get: G) -> RowWindowStats {
+    let cols = columns as usize;
+    let mut hmin = vec![f64::INFINITY; cols];
+    let mut hmax = vec![f64::NEG_INFINITY; cols];
+    let mut hsum = vec![0f64; cols];
+    let mut hcount = vec![0usize; cols];
+
+    let mut min_deque: VecDeque<isize> = VecDeque::new();
+    let mut max_deque: VecDeque<isize> = VecDeque::new();
+    let mut running_sum = 0f64;
+    let mut running_count = 0usize;
+
+    // Start `midpoint_x` columns before column 0 so that the window for
+    // column 0 (which spans `[-midpoint_x, midpoint_x]`) is already fully
+    // primed by the time we start recording output columns; only the
+    // recording below is gated on `col >= 0`, the add/remove bookkeeping
+    // runs for every column in between just as it does for column 0..columns.
+    for col in (-midpoint_x)..columns {
+        let arriving = col + midpoint_x;
+        let z_arriving = get(arriving);
+        if z_arriving != nodata {
+            while let Some(&back) = min_deque.back() {
+                if get(back) >= z_arriving { min_deque.pop_back(); } else { break; }
+            }
+            min_deque.push_back(arriving);
+            while let Some(&back) = max_deque.back() {
+                if get(back) <= z_arriving { max_deque.pop_back(); } else { break; }
+            }
+            max_deque.push_back(arriving);
+            running_sum += z_arriving;
+            running_count += 1;
+        }
+
+        let departing = col - midpoint_x - 1;
+        let z_departing = get(departing);
+        if z_departing != nodata {
+            running_sum -= z_departing;
+            running_count -= 1;
+        }
+        if min_deque.front() == Some(&departing) { min_deque.pop_front(); }
+        if max_deque.front() == Some(&departing) { max_deque.pop_front(); }
+
+        if col >= 0 {
+            let c = col as usize;
+            hmin[c] = min_deque.front().map(|&i| get(i)).unwrap_or(f64::INFINITY);
+            hmax[c] = max_deque.front().map(|&i| get(i)).unwrap_or(f64::NEG_INFINITY);
+            hsum[c] = running_sum;
+            hcount[c] = running_count;
+        }
+    }
+
+    RowWindowStats { row: row, hmin: hmin, hmax: hmax, hsum: hsum, hcount: hcount }
+}
+
+/// `horizontal_window_stats_core` specialized to read from a `Raster` row.
+fn horizontal_window_stats(input: &Raster, row: isize, columns: isize, midpoint_x: isize, nodata: f64) -> RowWindowStats {
+    horizontal_window_stats_core(row, columns, midpoint_x, nodata, |col| input[(row, col)])
+}
+
+/// The vertical half of the two-pass separable window: folds a stream of
+/// `RowWindowStats` (one per row, already horizontally windowed) down each
+/// column with the same monotonic-deque technique, so that `min`/`max`/
+/// `sum`/`count` always report the full rectangular window's statistics over
+/// every row currently held (pushed and not yet popped), in amortized O(1)
+/// per column.
+struct VerticalAggregator {
+    columns: usize,
+    min_deques: Vec<VecDeque<isize>>,
+    max_deques: Vec<VecDeque<isize>>,
+    running_sum: Vec<f64>,
+    running_count: Vec<usize>,
+    row_cache: VecDeque<RowWindowStats>,
+}
+
+impl VerticalAggregator {
+    fn new(columns: usize) -> VerticalAggregator {
+        VerticalAggregator {
+            columns: columns,
+            min_deques: vec![VecDeque::new(); columns],
+            max_deques: vec![VecDeque::new(); columns],
+            running_sum: vec![0f64; columns],
+            running_count: vec![0usize; columns],
+            row_cache: VecDeque::new(),
+        }
+    }
+
+    fn cached_hmin(&self, row: isize, col: usize) -> f64 {
+        let base = self.row_cache.front().unwrap().row;
+        self.row_cache[(row - base) as usize].hmin[col]
+    }
+
+    fn cached_hmax(&self, row: isize, col: usize) -> f64 {
+        let base = self.row_cache.front().unwrap().row;
+        self.row_cache[(row - base) as usize].hmax[col]
+    }
+
+    fn push_row(&mut self, stats: RowWindowStats) {
+        let row = stats.row;
+        for c in 0..self.columns {
+            if stats.hmin[c].is_finite() {
+                while let Some(&back) = self.min_deques[c].back() {
+                    if self.cached_hmin(back, c) >= stats.hmin[c] { self.min_deques[c].pop_back(); } else { break; }
+                }
+                self.min_deques[c].push_back(row);
+            }
+            if stats.hmax[c].is_finite() {
+                while let Some(&back) = self.max_deques[c].back() {
+                    if self.cached_hmax(back, c) <= stats.hmax[c] { self.max_deques[c].pop_back(); } else { break; }
+                }
+                self.max_deques[c].push_back(row);
+            }
+            self.running_sum[c] += stats.hsum[c];
+            self.running_count[c] += stats.hcount[c];
+        }
+        self.row_cache.push_back(stats);
+    }
+
+    fn pop_row(&mut self) {
+        let stats = self.row_cache.pop_front().unwrap();
+        let row = stats.row;
+        for c in 0..self.columns {
+            self.running_sum[c] -= stats.hsum[c];
+            self.running_count[c] -= stats.hcount[c];
+            if self.min_deques[c].front() == Some(&row) { self.min_deques[c].pop_front(); }
+            if self.max_deques[c].front() == Some(&row) { self.max_deques[c].pop_front(); }
+        }
+    }
+
+    fn min(&self, col: usize) -> f64 {
+        self.min_deques[col].front().map(|&r| self.cached_hmin(r, col)).unwrap_or(f64::INFINITY)
+    }
+
+    fn max(&self, col: usize) -> f64 {
+        self.max_deques[col].front().map(|&r| self.cached_hmax(r, col)).unwrap_or(f64::NEG_INFINITY)
+    }
+
+    fn sum(&self, col: usize) -> f64 {
+        self.running_sum[col]
+    }
+
+    fn count(&self, col: usize) -> usize {
+        self.running_count[col]
+    }
+}
+
 impl WhiteboxTool for OlympicFilter {
     fn get_tool_name(&self) -> String {
         self.name.clone()
@@ -71,54 +397,43 @@ impl WhiteboxTool for OlympicFilter {
     fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
         let mut input_file = String::new();
         let mut output_file = String::new();
-        let mut filter_size_x = 11usize;
-        let mut filter_size_y = 11usize;
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters. Please see help (-h) for parameter descriptions."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-filter" || vec[0].to_lowercase() == "--filter" {
-                if keyval {
-                    filter_size_x = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size_x = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-                filter_size_y = filter_size_x;
-            } else if vec[0].to_lowercase() == "-filterx" || vec[0].to_lowercase() == "--filterx" {
-                if keyval {
-                    filter_size_x = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size_x = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-filtery" || vec[0].to_lowercase() == "--filtery" {
-                if keyval {
-                    filter_size_y = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size_y = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-            }
+        // Flatten the raw CLI tokens into a key/value map first (catching a
+        // trailing flag with no value here, rather than panicking later on
+        // an out-of-bounds index), and separately collect an optional
+        // --params=file.json parameter file to merge underneath them.
+        let (cli_params, param_file) = split_args(&args)?;
+
+        let file_params = match param_file {
+            Some(ref path) => load_param_file(path)?,
+            None => HashMap::new(),
+        };
+
+        if let Some(v) = lookup_param(&cli_params, &file_params, &["i", "input"]) {
+            input_file = v;
         }
+        if let Some(v) = lookup_param(&cli_params, &file_params, &["o", "output"]) {
+            output_file = v;
+        }
+
+        // --filterx/--filtery each default to 11 independently, but an
+        // explicit --filter overrides both (see the parameter help text).
+        let filterx_param = UsizeParam::new(&["filterx"], 11, validate_filter_size);
+        let filtery_param = UsizeParam::new(&["filtery"], 11, validate_filter_size);
+        let filter_param = UsizeParam::new(&["filter"], 11, validate_filter_size);
+        let trim_param = UsizeParam::new(&["trim", "k"], 1, validate_trim);
+
+        let mut filter_size_x = filterx_param.resolve(&cli_params, &file_params)?;
+        let mut filter_size_y = filtery_param.resolve(&cli_params, &file_params)?;
+        if lookup_param(&cli_params, &file_params, &["filter"]).is_some() {
+            let n = filter_param.resolve(&cli_params, &file_params)?;
+            filter_size_x = n;
+            filter_size_y = n;
+        }
+        let trim_k = trim_param.resolve(&cli_params, &file_params)?;
 
         if verbose {
             println!("***************{}", "*".repeat(self.get_tool_name().len()));
@@ -128,18 +443,9 @@ impl WhiteboxTool for OlympicFilter {
 
         let sep: String = path::MAIN_SEPARATOR.to_string();
 
-        if filter_size_x < 3 { filter_size_x = 3; }
-        if filter_size_y < 3 { filter_size_y = 3; }
-
-        // The filter dimensions must be odd numbers such that there is a middle pixel
-        if (filter_size_x as f64 / 2f64).floor() == (filter_size_x as f64 / 2f64) {
-            filter_size_x += 1;
-        }
-        if (filter_size_y as f64 / 2f64).floor() == (filter_size_y as f64 / 2f64) {
-            filter_size_y += 1;
-        }
-
-        // let (mut z, mut z_n): (f64, f64);
+        // filter_size_x/filter_size_y are either the odd, >= 3 defaults above
+        // or have already been rejected by validate_filter_size, so there is
+        // nothing left to clamp or round here.
         let midpoint_x = (filter_size_x as f64 / 2f64).floor() as isize;
         let midpoint_y = (filter_size_y as f64 / 2f64).floor() as isize;
         let mut progress: usize;
@@ -161,6 +467,14 @@ impl WhiteboxTool for OlympicFilter {
         let mut output = Raster::initialize_using_file(&output_file, &input);
         let rows = input.configs.rows as isize;
 
+        let data_min = input.configs.minimum;
+        let data_max = input.configs.maximum;
+        let bin_width = if data_max > data_min {
+            (data_max - data_min) / NUM_HISTOGRAM_BINS as f64
+        } else {
+            1f64
+        };
+
         let mut starting_row;
         let mut ending_row = 0;
         let num_procs = num_cpus::get() as isize;
@@ -180,89 +494,84 @@ impl WhiteboxTool for OlympicFilter {
             thread::spawn(move || {
                 let nodata = input.configs.nodata;
                 let columns = input.configs.columns as isize;
-                let (mut z_n, mut z) : (f64, f64);
-                let (mut min_val, mut max_val): (f64, f64);
-                let (mut start_col, mut end_col, mut start_row, mut end_row): (isize, isize, isize, isize);
-                let mut sum: f64;
-                let mut mean: f64;
-                let mut n: usize;
+                let mut z: f64;
+                let mut aggregator = VerticalAggregator::new(columns as usize);
+                // The Huang sliding histogram is only needed to find a general
+                // trim cut-off (k > 1); the classic olympic filter (k <= 1) is
+                // answered directly by the separable min/max/sum/count above.
+                // `col_hist_cache` maintains every column's vertical-window
+                // histogram incrementally across row advances (mirroring
+                // `aggregator`'s push_row/pop_row), so the per-row horizontal
+                // slide below only ever touches O(columns) bins, regardless of
+                // `filter_size_y`.
+                let mut col_hist_cache: Option<ColumnHistogramCache> = None;
                 for row in starting_row..ending_row {
-                    let mut filter_min_vals: VecDeque<f64> = VecDeque::with_capacity(filter_size_x);
-                    let mut filter_max_vals: VecDeque<f64> = VecDeque::with_capacity(filter_size_x);
-                    let mut totals: VecDeque<f64> = VecDeque::with_capacity(filter_size_x);
-                    let mut n_vals: VecDeque<usize> = VecDeque::with_capacity(filter_size_x);
-                    start_row = row - midpoint_y;
-                    end_row = row + midpoint_y;
+                    // The vertical pass only needs to push the newly-arrived row and
+                    // pop the one that has fallen out of the window; min/max/sum/count
+                    // for the full rectangle then come straight out of the aggregator.
+                    if row == starting_row {
+                        for row2 in (row - midpoint_y)..(row + midpoint_y + 1) {
+                            aggregator.push_row(horizontal_window_stats(&input, row2, columns, midpoint_x, nodata));
+                        }
+                        if trim_k > 1 {
+                            col_hist_cache = Some(ColumnHistogramCache::new(&input, columns as usize,
+                                row - midpoint_y, row + midpoint_y, nodata, data_min, bin_width, NUM_HISTOGRAM_BINS));
+                        }
+                    } else {
+                        aggregator.push_row(horizontal_window_stats(&input, row + midpoint_y, columns, midpoint_x, nodata));
+                        aggregator.pop_row();
+                        if let Some(ref mut cache) = col_hist_cache {
+                            cache.advance_row(&input, row - midpoint_y - 1, row + midpoint_y, nodata);
+                        }
+                    }
+
+                    let mut window_hist: Vec<u32> = Vec::new();
+                    if let Some(ref cache) = col_hist_cache {
+                        window_hist = vec![0u32; NUM_HISTOGRAM_BINS];
+                        for col2 in (0 - midpoint_x)..(midpoint_x + 1) {
+                            let col_hist = cache.get(col2);
+                            for b in 0..NUM_HISTOGRAM_BINS {
+                                window_hist[b] += col_hist[b];
+                            }
+                        }
+                    }
+
                     let mut data = vec![nodata; columns as usize];
                     for col in 0..columns {
-                        if col > 0 {
-                            filter_min_vals.pop_front();
-                            filter_max_vals.pop_front();
-                            totals.pop_front();
-                            n_vals.pop_front();
-                            min_val = f64::INFINITY;
-                            max_val = f64::NEG_INFINITY;
-                            sum = 0.0;
-                            n = 0;
-                            for row2 in start_row..end_row+1 {
-                                z_n = input.get_value(row2, col + midpoint_x);
-                                if z_n != nodata {
-                                    if z_n < min_val { min_val = z_n; }
-                                    if z_n > max_val { max_val = z_n; }
-                                    sum += z_n;
-                                    n += 1;
+                        if let Some(ref cache) = col_hist_cache {
+                            if col > 0 {
+                                let departing_hist = cache.get(col - midpoint_x - 1);
+                                let arriving_hist = cache.get(col + midpoint_x);
+                                for b in 0..NUM_HISTOGRAM_BINS {
+                                    window_hist[b] = window_hist[b] - departing_hist[b] + arriving_hist[b];
                                 }
                             }
-                            filter_min_vals.push_back(min_val);
-                            filter_max_vals.push_back(max_val);
-                            totals.push_back(sum);
-                            n_vals.push_back(n);
-                        } else {
-                            // initialize the filter_vals
-                            start_col = col - midpoint_x;
-                            end_col = col + midpoint_x;
-                            for col2 in start_col..end_col+1 {
-                                min_val = f64::INFINITY;
-                                max_val = f64::NEG_INFINITY;
-                                sum = 0.0;
-                                n = 0;
-                                for row2 in start_row..end_row+1 {
-                                    z_n = input[(row2, col2)];
-                                    if z_n != nodata {
-                                        if z_n < min_val { min_val = z_n; }
-                                        if z_n > max_val { max_val = z_n; }
-                                        sum += z_n;
-                                        n += 1;
-                                    }
-                                }
-                                filter_min_vals.push_back(min_val);
-                                filter_max_vals.push_back(max_val);
-                                totals.push_back(sum);
-                                n_vals.push_back(n);
-                            }
                         }
+
                         z = input[(row, col)];
                         if z != nodata {
-                            min_val = f64::INFINITY;
-                            max_val = f64::NEG_INFINITY;
-                            sum = 0.0;
-                            n = 0;
-                            for i in 0..filter_size_x {
-                                if filter_min_vals[i] < min_val { 
-                                    min_val = filter_min_vals[i]; 
-                                }
-                                if filter_max_vals[i] > max_val { 
-                                    max_val = filter_max_vals[i]; 
-                                }
-                                sum += totals[i];
-                                n += n_vals[i];
-                            }
-                            if n > 2 {
-                                mean = (sum - max_val - min_val) / (n - 2) as f64; // this is a special mean that removes the highest and lowest values.
-                                data[col as usize] = mean;
-                            } else {
-                                // This should only rarely occur
-                                data[col as usize] = sum / n as f64;
+                            let c = col as usize;
+                            let n = aggregator.count(c);
+                            if n > 0 {
+                                let sum = aggregator.sum(c);
+                                data[c] = if trim_k == 0 {
+                                    sum / n as f64
+                                } else if trim_k == 1 {
+                                    if n > 2 {
+                                        // this is a special mean that removes the highest and lowest values.
+                                        (sum - aggregator.max(c) - aggregator.min(c)) / (n - 2) as f64
+                                    } else {
+                                        // This should only rarely occur
+                                        sum / n as f64
+                                    }
+                                } else if n > 2 * trim_k {
+                                    // a general alpha-trimmed mean that discards the k
+                                    // lowest and k highest values before averaging.
+                                    trimmed_mean_from_histogram(&window_hist, trim_k, data_min, bin_width)
+                                } else {
+                                    // This should only rarely occur
+                                    sum / n as f64
+                                };
                             }
                         }
                     }
@@ -289,6 +598,7 @@ impl WhiteboxTool for OlympicFilter {
         output.add_metadata_entry(format!("Input file: {}", input_file));
         output.add_metadata_entry(format!("Filter size x: {}", filter_size_x));
         output.add_metadata_entry(format!("Filter size y: {}", filter_size_y));
+        output.add_metadata_entry(format!("Trim (k): {}", trim_k));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
         if verbose { println!("Saving data...") };
@@ -301,4 +611,104 @@ impl WhiteboxTool for OlympicFilter {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NODATA: f64 = -32768f64;
+
+    /// Straightforwardly recomputes min/max/sum/count for `row`'s horizontal
+    /// window by rescanning `[col - midpoint_x, col + midpoint_x]` for every
+    /// column, with no sliding state kept between columns. Used to check
+    /// `horizontal_window_stats_core`'s amortized O(1) version column by
+    /// column.
+    fn brute_force_horizontal_stats(data: &[f64], columns: isize, midpoint_x: isize, nodata: f64) -> RowWindowStats {
+        let get = |col: isize| -> f64 {
+            if col < 0 || col >= columns { nodata } else { data[col as usize] }
+        };
+        let cols = columns as usize;
+        let mut hmin = vec![f64::INFINITY; cols];
+        let mut hmax = vec![f64::NEG_INFINITY; cols];
+        let mut hsum = vec![0f64; cols];
+        let mut hcount = vec![0usize; cols];
+        for col in 0..columns {
+            for c in (col - midpoint_x)..(col + midpoint_x + 1) {
+                let z = get(c);
+                if z != nodata {
+                    let i = col as usize;
+                    if z < hmin[i] { hmin[i] = z; }
+                    if z > hmax[i] { hmax[i] = z; }
+                    hsum[i] += z;
+                    hcount[i] += 1;
+                }
+            }
+        }
+        RowWindowStats { row: 0, hmin: hmin, hmax: hmax, hsum: hsum, hcount: hcount }
+    }
+
+    #[test]
+    fn horizontal_window_stats_matches_brute_force_with_nodata_gaps() {
+        let data = vec![5.0, NODATA, 2.0, 8.0, 1.0, NODATA, NODATA, 4.0, 9.0, 3.0];
+        let columns = data.len() as isize;
+        let midpoint_x = 2;
+        let get = |col: isize| -> f64 {
+            if col < 0 || col >= columns { NODATA } else { data[col as usize] }
+        };
+        let actual = horizontal_window_stats_core(0, columns, midpoint_x, NODATA, get);
+        let expected = brute_force_horizontal_stats(&data, columns, midpoint_x, NODATA);
+        assert_eq!(actual.hmin, expected.hmin);
+        assert_eq!(actual.hmax, expected.hmax);
+        assert_eq!(actual.hsum, expected.hsum);
+        assert_eq!(actual.hcount, expected.hcount);
+    }
+
+    #[test]
+    fn horizontal_window_stats_handles_an_all_nodata_window() {
+        let data = vec![NODATA; 5];
+        let columns = data.len() as isize;
+        let get = |col: isize| -> f64 {
+            if col < 0 || col >= columns { NODATA } else { data[col as usize] }
+        };
+        let stats = horizontal_window_stats_core(0, columns, 1, NODATA, get);
+        assert_eq!(stats.hcount, vec![0usize; 5]);
+        assert_eq!(stats.hsum, vec![0f64; 5]);
+    }
+
+    /// Recomputes the alpha-trimmed mean directly by sorting the expanded
+    /// sample list and discarding the `k` lowest/highest, to check
+    /// `trimmed_mean_from_histogram`'s binned version against ground truth.
+    fn brute_force_trimmed_mean(values: &[f64], k: usize) -> f64 {
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let kept = &sorted[k..sorted.len() - k];
+        kept.iter().sum::<f64>() / kept.len() as f64
+    }
+
+    #[test]
+    fn trimmed_mean_from_histogram_matches_brute_force() {
+        let values = vec![3.0, 1.0, 7.0, 2.0, 9.0, 4.0, 6.0, 5.0, 8.0, 0.0];
+        let data_min = 0f64;
+        let data_max = 10f64;
+        let num_bins = 256;
+        let bin_width = (data_max - data_min) / num_bins as f64;
+        let mut hist = vec![0u32; num_bins];
+        for &v in &values {
+            hist[histogram_bin(v, data_min, bin_width, num_bins)] += 1;
+        }
+        for k in 1..4 {
+            let actual = trimmed_mean_from_histogram(&hist, k, data_min, bin_width);
+            let expected = brute_force_trimmed_mean(&values, k);
+            assert!((actual - expected).abs() < bin_width,
+                "k={}: expected {} got {}", k, expected, actual);
+        }
+    }
+
+    #[test]
+    fn histogram_bin_clamps_to_the_first_and_last_bin() {
+        assert_eq!(histogram_bin(-5.0, 0.0, 1.0, 10), 0);
+        assert_eq!(histogram_bin(100.0, 0.0, 1.0, 10), 9);
+        assert_eq!(histogram_bin(5.5, 0.0, 1.0, 10), 5);
+    }
+}
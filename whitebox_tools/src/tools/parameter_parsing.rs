@@ -0,0 +1,211 @@
+/*
+This module is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: July 26, 2026
+Last Modified: July 26, 2026
+License: MIT
+*/
+//! Shared argument parsing/validation layer for `WhiteboxTool::run`
+//! implementations. Each tool declares its own parameters (name, aliases,
+//! type, default and a validator closure) as a `UsizeParam` (more parameter
+//! types can be added the same way as tools need them) and resolves them
+//! against the merged CLI/params-file values produced by `split_args` and
+//! `load_param_file`, rather than hand-rolling `.unwrap()`-based parsing.
+extern crate serde_json;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read};
+
+pub type UsizeValidator = fn(usize) -> Result<(), String>;
+
+/// A `usize`-typed tool parameter: the CLI/JSON aliases it can be supplied
+/// under (e.g. `["filter"]`, or `["trim", "k"]`), the value to fall back on
+/// when it isn't supplied at all, and a validator that turns an out-of-range
+/// value into a message naming what went wrong.
+pub struct UsizeParam {
+    pub aliases: &'static [&'static str],
+    pub default: usize,
+    pub validator: UsizeValidator,
+}
+
+impl UsizeParam {
+    pub fn new(aliases: &'static [&'static str], default: usize, validator: UsizeValidator) -> UsizeParam {
+        UsizeParam { aliases: aliases, default: default, validator: validator }
+    }
+
+    /// Looks the parameter up in `cli` (preferred) then `file`, parses and
+    /// validates it, and falls back to `self.default` if it wasn't supplied.
+    pub fn resolve(&self, cli: &HashMap<String, String>, file: &HashMap<String, String>) -> Result<usize, Error> {
+        match lookup_param(cli, file, self.aliases) {
+            Some(v) => parse_validated_usize(self.aliases[0], &v, self.validator),
+            None => Ok(self.default),
+        }
+    }
+}
+
+/// Splits raw CLI tokens (as received by `WhiteboxTool::run`) into a
+/// lower-cased key/value map, pulling out an optional `--params`/`-params`
+/// JSON parameter file path along the way. Supports both `--flag=value` and
+/// space-separated `--flag value` forms; in the latter form the value token
+/// is consumed and skipped rather than re-interpreted as its own flag on the
+/// next iteration. A flag with no following value returns a clear `Error`
+/// instead of letting the caller panic on an out-of-bounds index.
+pub fn split_args(args: &[String]) -> Result<(HashMap<String, String>, Option<String>), Error> {
+    let mut cli_params: HashMap<String, String> = HashMap::new();
+    let mut param_file: Option<String> = None;
+    let mut i = 0;
+    while i < args.len() {
+        let mut arg = args[i].replace("\"", "");
+        arg = arg.replace("\'", "");
+        let cmd = arg.split("="); // in case an equals sign was used
+        let vec = cmd.collect::<Vec<&str>>();
+        let key = vec[0].trim_start_matches('-').to_lowercase();
+        let value = if vec.len() > 1 {
+            vec[1].to_string()
+        } else {
+            match args.get(i + 1) {
+                Some(v) => {
+                    i += 1;
+                    v.to_string()
+                }
+                None => return Err(Error::new(ErrorKind::InvalidInput,
+                    format!("Parameter '{}' is missing a value.", vec[0]))),
+            }
+        };
+        if key == "params" {
+            param_file = Some(value);
+        } else {
+            cli_params.insert(key, value);
+        }
+        i += 1;
+    }
+    Ok((cli_params, param_file))
+}
+
+/// Reads a `--params=file.json` parameter file and flattens its top-level
+/// object into a lower-cased key/value map, mirroring the keys accepted on
+/// the command line (e.g. `{"filter": 25}` is equivalent to `--filter=25`).
+pub fn load_param_file(path: &str) -> Result<HashMap<String, String>, Error> {
+    let mut contents = String::new();
+    File::open(path)?.read_to_string(&mut contents)?;
+    let json: serde_json::Value = serde_json::from_str(&contents).map_err(|e| {
+        Error::new(ErrorKind::InvalidInput, format!("Could not parse parameter file '{}': {}", path, e))
+    })?;
+    let mut map = HashMap::new();
+    if let serde_json::Value::Object(obj) = json {
+        for (key, value) in obj {
+            let value_str = match value {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            };
+            map.insert(key.to_lowercase(), value_str);
+        }
+    }
+    Ok(map)
+}
+
+/// Looks up `keys` (aliases for the same logical parameter) in `cli`,
+/// falling back to `file` so that command-line arguments override a
+/// `--params` file rather than the other way around.
+pub fn lookup_param(cli: &HashMap<String, String>, file: &HashMap<String, String>, keys: &[&str]) -> Option<String> {
+    for key in keys {
+        if let Some(v) = cli.get(*key) {
+            return Some(v.clone());
+        }
+    }
+    for key in keys {
+        if let Some(v) = file.get(*key) {
+            return Some(v.clone());
+        }
+    }
+    None
+}
+
+/// Parses and validates a `usize`-typed parameter, turning a malformed value
+/// into a descriptive `Error` naming the offending parameter instead of
+/// letting `.unwrap()` panic the whole process.
+pub fn parse_validated_usize(param_name: &str, value: &str, validator: UsizeValidator) -> Result<usize, Error> {
+    let parsed = value.parse::<usize>().map_err(|_| {
+        Error::new(ErrorKind::InvalidInput,
+            format!("Invalid value '{}' for parameter '--{}': expected a non-negative integer.", value, param_name))
+    })?;
+    validator(parsed).map_err(|msg| {
+        Error::new(ErrorKind::InvalidInput, format!("Invalid value for parameter '--{}': {}", param_name, msg))
+    })?;
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_args_merges_keyval_and_positional_forms() {
+        let args = vec!["--filter=25".to_string(), "--trim".to_string(), "2".to_string()];
+        let (cli, param_file) = split_args(&args).unwrap();
+        assert_eq!(cli.get("filter").map(|s| s.as_str()), Some("25"));
+        assert_eq!(cli.get("trim").map(|s| s.as_str()), Some("2"));
+        assert_eq!(param_file, None);
+    }
+
+    #[test]
+    fn split_args_rejects_a_trailing_flag_with_no_value() {
+        let args = vec!["--filter".to_string()];
+        assert!(split_args(&args).is_err());
+    }
+
+    #[test]
+    fn split_args_handles_space_separated_values_without_misreading_them_as_flags() {
+        let args = vec!["--filter".to_string(), "25".to_string()];
+        let (cli, param_file) = split_args(&args).unwrap();
+        assert_eq!(cli.get("filter").map(|s| s.as_str()), Some("25"));
+        assert_eq!(param_file, None);
+    }
+
+    #[test]
+    fn split_args_handles_multiple_space_separated_flags_in_sequence() {
+        let args = vec![
+            "-i".to_string(), "in.tif".to_string(),
+            "-o".to_string(), "out.tif".to_string(),
+            "--filter".to_string(), "25".to_string(),
+        ];
+        let (cli, _) = split_args(&args).unwrap();
+        assert_eq!(cli.get("i").map(|s| s.as_str()), Some("in.tif"));
+        assert_eq!(cli.get("o").map(|s| s.as_str()), Some("out.tif"));
+        assert_eq!(cli.get("filter").map(|s| s.as_str()), Some("25"));
+    }
+
+    #[test]
+    fn usize_param_falls_back_to_default_when_absent() {
+        let cli = HashMap::new();
+        let file = HashMap::new();
+        let param = UsizeParam::new(&["trim", "k"], 1, |_| Ok(()));
+        assert_eq!(param.resolve(&cli, &file).unwrap(), 1);
+    }
+
+    #[test]
+    fn usize_param_reports_the_parameter_name_on_validation_failure() {
+        let mut cli = HashMap::new();
+        cli.insert("filter".to_string(), "4".to_string());
+        let file = HashMap::new();
+        let param = UsizeParam::new(&["filter"], 11, |n| {
+            if n < 3 || n % 2 == 0 {
+                Err("filter size must be a positive odd integer >= 3.".to_string())
+            } else {
+                Ok(())
+            }
+        });
+        let err = param.resolve(&cli, &file).unwrap_err();
+        assert!(format!("{}", err).contains("filter"));
+    }
+
+    #[test]
+    fn cli_value_overrides_params_file_value() {
+        let mut cli = HashMap::new();
+        cli.insert("filter".to_string(), "25".to_string());
+        let mut file = HashMap::new();
+        file.insert("filter".to_string(), "11".to_string());
+        assert_eq!(lookup_param(&cli, &file, &["filter"]), Some("25".to_string()));
+    }
+}